@@ -14,6 +14,7 @@ use crate::collections::{ArrayDeque, CInlineVec};
 use crate::graph::local::coord::LocalNodeIndex;
 use crate::graph::local::*;
 use crate::graph::octree::LinearBitOctree;
+use crate::graph::storage::SparseColumnStore;
 use crate::graph::visibility::*;
 use crate::math::*;
 use crate::mem::InitDefaultInPlace;
@@ -22,14 +23,21 @@ use crate::region::*;
 pub mod flags;
 pub mod local;
 mod octree;
+mod storage;
 pub mod visibility;
 
 pub const SECTIONS_IN_GRAPH: usize = 256 * 256 * 256;
 
+// upper bound on how many sections a pool of chunk-build worker threads can
+// realistically finish building between two calls to `cull_and_sort`
+pub const MAX_PENDING_SECTION_UPDATES: usize = 4096;
+
 pub const MAX_VIEW_DISTANCE: u8 = 127;
-pub const MAX_WORLD_HEIGHT: u8 = 254;
+// the tallest world height, in sections, the statically-sized BFS queue is
+// sized to support
+pub const MAX_SUPPORTED_WORLD_HEIGHT_SECTIONS: u8 = 254;
 pub const BFS_QUEUE_SIZE: usize =
-    get_bfs_queue_max_size(MAX_VIEW_DISTANCE, MAX_WORLD_HEIGHT) as usize;
+    get_bfs_queue_max_size(MAX_VIEW_DISTANCE, MAX_SUPPORTED_WORLD_HEIGHT_SECTIONS) as usize;
 pub type BfsQueue = ArrayDeque<LocalNodeIndex<0>, BFS_QUEUE_SIZE>;
 pub type SortedRegionRenderLists = CInlineVec<RegionRenderList, REGIONS_IN_GRAPH>;
 
@@ -77,7 +85,7 @@ pub const fn get_bfs_queue_max_size(section_render_distance: u8, world_height: u
 
 #[derive(InitDefaultInPlace)]
 pub struct BfsCachedState {
-    incoming_directions: [GraphDirectionSet; SECTIONS_IN_GRAPH],
+    incoming_directions: SparseColumnStore<GraphDirectionSet>,
     staging_render_lists: StagingRegionRenderLists,
     queue_1: BfsQueue,
     queue_2: BfsQueue,
@@ -85,7 +93,7 @@ pub struct BfsCachedState {
 
 impl BfsCachedState {
     pub fn reset(&mut self) {
-        self.incoming_directions.fill(GraphDirectionSet::NONE);
+        self.incoming_directions.reset();
         self.staging_render_lists.clear();
         // TODO: are these necessary?
         self.queue_1.reset();
@@ -104,10 +112,22 @@ impl FrustumFogCachedState {
     }
 }
 
+// A section update that has been queued but not yet applied to the graph.
+// Queued updates are applied together at the start of `cull_and_sort`.
+// Queuing one takes `&mut Graph`, so it is serialized with `cull_and_sort`
+// just as `set_section` is; the queue does not allow updates from other
+// threads while culling runs.
+#[derive(Clone, Copy)]
+enum PendingSectionUpdate {
+    Set(i32x3, VisibilityData),
+    Remove(i32x3),
+}
+
 #[derive(InitDefaultInPlace)]
 pub struct Graph {
-    section_visibility_direction_sets: [VisibilityData; SECTIONS_IN_GRAPH],
+    section_visibility_direction_sets: SparseColumnStore<VisibilityData>,
     // section_flag_sets: [SectionFlagSet; SECTIONS_IN_GRAPH],
+    pending_section_updates: CInlineVec<PendingSectionUpdate, MAX_PENDING_SECTION_UPDATES>,
     frustum_fog_cached_state: FrustumFogCachedState,
     bfs_cached_state: BfsCachedState,
 
@@ -115,21 +135,81 @@ pub struct Graph {
 }
 
 impl Graph {
-    pub fn new_boxed() -> Box<Self> {
+    // `world_height_sections` is the height, in sections, of the world this
+    // graph is backing; it sizes the dense per-column runs both sparse
+    // section stores lazily allocate, so a typical world pays for its own
+    // height instead of the full 256-section packed range. It must not
+    // exceed `MAX_SUPPORTED_WORLD_HEIGHT_SECTIONS`, which is what the
+    // statically sized `BfsQueue` was allocated for.
+    pub fn new_boxed(world_height_sections: u8) -> Box<Self> {
+        debug_assert!(world_height_sections <= MAX_SUPPORTED_WORLD_HEIGHT_SECTIONS);
+
         unsafe {
             let uninit = alloc(Layout::new::<Graph>()) as *mut Graph;
 
             uninit.init_default_in_place();
 
+            (*uninit)
+                .section_visibility_direction_sets
+                .set_world_height(world_height_sections);
+            (*uninit)
+                .bfs_cached_state
+                .incoming_directions
+                .set_world_height(world_height_sections);
+
             Box::from_raw(uninit)
         }
     }
 
+    // Queues a section update to be applied on the next `apply_pending_updates`
+    // (which `cull_and_sort` calls at the start of every invocation), instead
+    // of mutating `section_visibility_direction_sets` immediately. This mirrors
+    // how freshly built sections are staged from worker threads and ingested
+    // once per tick, rather than applied as they trickle in.
+    pub fn queue_set_section(&mut self, section_coord: i32x3, visibility_data: VisibilityData) {
+        self.pending_section_updates
+            .push(PendingSectionUpdate::Set(section_coord, visibility_data));
+    }
+
+    pub fn queue_remove_section(&mut self, section_coord: i32x3) {
+        self.pending_section_updates
+            .push(PendingSectionUpdate::Remove(section_coord));
+    }
+
+    // Drains and applies all queued section updates in the order they were
+    // queued, so repeated edits to the same section collapse to the last
+    // value. Called automatically at the start of `cull_and_sort`, but also
+    // exposed so the apply step can be triggered independently of culling.
+    pub fn apply_pending_updates(&mut self) {
+        // `PendingSectionUpdate` is `Copy`, so each iteration copies the update
+        // out of `pending_section_updates` before calling into `set_section`/
+        // `remove_section`, rather than holding a borrow of the vec across
+        // those `&mut self` calls.
+        let update_count = self.pending_section_updates.element_count();
+
+        for i in 0..update_count {
+            let update = self.pending_section_updates.get_slice()[i];
+
+            match update {
+                PendingSectionUpdate::Set(section_coord, visibility_data) => {
+                    self.set_section(section_coord, visibility_data)
+                }
+                PendingSectionUpdate::Remove(section_coord) => {
+                    self.remove_section(section_coord)
+                }
+            }
+        }
+
+        self.pending_section_updates.clear();
+    }
+
     pub fn cull_and_sort(
         &mut self,
         coord_context: &LocalCoordContext,
         use_occlusion_culling: bool,
     ) -> &SortedRegionRenderLists {
+        self.apply_pending_updates();
+
         self.results.clear();
 
         self.frustum_and_fog_cull(coord_context);
@@ -224,8 +304,9 @@ impl Graph {
 
         // All incoming directions are set for the first section to make sure we try all
         // of its outgoing directions.
-        initial_node_index
-            .index_array_unchecked_mut(&mut self.bfs_cached_state.incoming_directions)
+        self.bfs_cached_state
+            .incoming_directions
+            .get_mut_or_insert(initial_node_index.unpack())
             .add_all(GraphDirectionSet::ALL);
 
         let mut finished = false;
@@ -260,11 +341,14 @@ impl Graph {
 
                 // use incoming directions to determine outgoing directions, given the
                 // visibility bits set
-                let section_incoming_directions = *local_section_index
-                    .index_array_unchecked(&self.bfs_cached_state.incoming_directions);
+                let section_incoming_directions = self
+                    .bfs_cached_state
+                    .incoming_directions
+                    .get(local_section_coords);
 
-                let mut section_outgoing_directions = local_section_index
-                    .index_array_unchecked(&self.section_visibility_direction_sets)
+                let mut section_outgoing_directions = self
+                    .section_visibility_direction_sets
+                    .get(local_section_coords)
                     .get_outgoing_directions(section_incoming_directions);
                 section_outgoing_directions.add_all(directions_modifier);
                 section_outgoing_directions &=
@@ -274,15 +358,65 @@ impl Graph {
                 // enqueued
                 let section_neighbor_indices = local_section_index.get_all_neighbors();
 
+                // Up/Down neighbors share this section's (x, z) column, so resolve their
+                // rows before taking the column itself mutably, then write through the
+                // column directly instead of paying a fresh column lookup per vertical
+                // neighbor.
+                {
+                    let vertical_targets = [GraphDirection::Up, GraphDirection::Down].map(|direction| {
+                        let in_outgoing =
+                            !(GraphDirectionSet::single(direction) & section_outgoing_directions).is_empty();
+
+                        in_outgoing.then(|| {
+                            let neighbor_index = section_neighbor_indices.get(direction);
+                            let row = self
+                                .bfs_cached_state
+                                .incoming_directions
+                                .row_of(neighbor_index.unpack());
+
+                            (neighbor_index, row)
+                        })
+                    });
+
+                    let current_column = self
+                        .bfs_cached_state
+                        .incoming_directions
+                        .get_or_insert_column_mut(local_section_coords);
+
+                    for (direction, target) in
+                        [GraphDirection::Up, GraphDirection::Down].into_iter().zip(vertical_targets)
+                    {
+                        let Some((neighbor_index, row)) = target else {
+                            continue;
+                        };
+
+                        let current_incoming_direction = direction.opposite();
+                        let neighbor_incoming_directions = &mut current_column[row];
+
+                        // enqueue only if the node has not yet been enqueued, avoiding duplicates
+                        let should_enqueue = neighbor_incoming_directions.is_empty();
+
+                        neighbor_incoming_directions.add(current_incoming_direction);
+
+                        write_queue_ref.push_conditionally(neighbor_index, should_enqueue);
+                    }
+                }
+
                 for direction in section_outgoing_directions {
+                    if direction == GraphDirection::Up || direction == GraphDirection::Down {
+                        continue;
+                    }
+
                     let neighbor_index = section_neighbor_indices.get(direction);
 
                     // the outgoing direction for the current node is the incoming direction for the
                     // neighbor
                     let current_incoming_direction = direction.opposite();
 
-                    let neighbor_incoming_directions = neighbor_index
-                        .index_array_unchecked_mut(&mut self.bfs_cached_state.incoming_directions);
+                    let neighbor_incoming_directions = self
+                        .bfs_cached_state
+                        .incoming_directions
+                        .get_mut_or_insert(neighbor_index.unpack());
 
                     // enqueue only if the node has not yet been enqueued, avoiding duplicates
                     let should_enqueue = neighbor_incoming_directions.is_empty();
@@ -307,8 +441,8 @@ impl Graph {
         let index = LocalNodeIndex::<0>::pack(local_coord);
 
         // *index.index_array_unchecked_mut(&mut self.section_flag_sets) = flags;
-        *index.index_array_unchecked_mut(&mut self.section_visibility_direction_sets) =
-            visibility_data;
+        self.section_visibility_direction_sets
+            .set(index.unpack(), visibility_data);
     }
 
     pub fn remove_section(&mut self, section_coord: i32x3) {
@@ -0,0 +1,173 @@
+use alloc::alloc::{alloc_zeroed, Layout};
+use alloc::boxed::Box;
+use alloc::vec;
+
+use core_simd::simd::*;
+
+use crate::mem::InitDefaultInPlace;
+
+/// Number of distinct values a packed coordinate axis can take (`u8`'s range).
+const AXIS_RANGE: usize = 256;
+/// Total number of distinct (x, z) columns addressable by a packed local
+/// section coordinate.
+const COLUMN_COUNT: usize = AXIS_RANGE * AXIS_RANGE;
+
+#[inline(always)]
+fn column_slot(local_section_coord: u8x3) -> usize {
+    local_section_coord.x() as usize * AXIS_RANGE + local_section_coord.z() as usize
+}
+
+/// Column-keyed, sparse backing store for per-section data.
+///
+/// A column is resolved by directly indexing a flat table of `COLUMN_COUNT`
+/// slots with the section's (x, z) coordinates -- no hashing or tree
+/// traversal, so resolving a column is as cheap as the old dense array index
+/// was. Each slot lazily holds a boxed run of `T`, [`set_world_height`]
+/// sections long, once that column is actually touched; an untouched column
+/// costs only its two-word (fat pointer sized) `None` slot in the outer table, rather than
+/// the `T` run itself. Memory therefore scales with the number of columns
+/// actually touched and the world's configured height, not a fixed
+/// `256*256*256`.
+///
+/// The y axis of a packed local coordinate still only spans `0..256` (a
+/// `LocalNodeIndex` packs each axis into a `u8`); this store does not lift
+/// that bound. What it does do is translate a section's local y into an
+/// offset relative to the world's vertical center ([`set_world_height`]) so
+/// a column's run can be sized to the world's actual height instead of the
+/// full 256. A y outside that run (BFS straying past the configured world's
+/// top or bottom) is clamped to the nearer end of the run rather than
+/// indexed out of bounds.
+///
+/// Within an already-loaded column, indexing is a direct array index; only
+/// resolving *which* column a coordinate belongs to costs an array lookup,
+/// so callers that process several sections sharing a column (as the BFS
+/// inner loop does for its vertical neighbors) should resolve that column
+/// once with [`get_or_insert_column_mut`] and index into it directly via
+/// [`row_of`], rather than paying a fresh lookup per section.
+///
+/// [`set_world_height`]: SparseColumnStore::set_world_height
+/// [`get_or_insert_column_mut`]: SparseColumnStore::get_or_insert_column_mut
+/// [`row_of`]: SparseColumnStore::row_of
+pub struct SparseColumnStore<T> {
+    world_height_sections: usize,
+    y_base: usize,
+    columns: Box<[Option<Box<[T]>>; COLUMN_COUNT]>,
+}
+
+impl<T: Copy + Default> SparseColumnStore<T> {
+    /// Sets the number of sections tall a loaded column's run is, and
+    /// recenters the y-offset mapping around the world's vertical middle,
+    /// mirroring the centered-world assumption [`get_bfs_queue_max_size`]
+    /// already makes. Drops every currently loaded column, since they were
+    /// sized and offset for whatever height was previously configured.
+    ///
+    /// [`get_bfs_queue_max_size`]: super::get_bfs_queue_max_size
+    pub fn set_world_height(&mut self, world_height_sections: u8) {
+        self.world_height_sections = world_height_sections as usize;
+        self.y_base = (AXIS_RANGE - self.world_height_sections) / 2;
+        self.clear();
+    }
+
+    /// Maps a packed local y into the row index of a `world_height_sections`
+    /// tall column, clamping to the nearer end of the run if the y falls
+    /// outside the configured world's height.
+    #[inline]
+    pub fn row_of(&self, local_section_coord: u8x3) -> usize {
+        let y = local_section_coord.y() as usize;
+
+        y.saturating_sub(self.y_base)
+            .min(self.world_height_sections - 1)
+    }
+
+    #[inline]
+    pub fn get(&self, local_section_coord: u8x3) -> T {
+        let slot = column_slot(local_section_coord);
+        let row = self.row_of(local_section_coord);
+
+        self.columns[slot]
+            .as_deref()
+            .map_or_else(Default::default, |column| column[row])
+    }
+
+    #[inline]
+    pub fn set(&mut self, local_section_coord: u8x3, value: T) {
+        let row = self.row_of(local_section_coord);
+
+        self.get_or_insert_column_mut(local_section_coord)[row] = value;
+    }
+
+    /// Returns a mutable reference to the slot for `local_section_coord`,
+    /// lazily allocating its column's dense run on first touch.
+    #[inline]
+    pub fn get_mut_or_insert(&mut self, local_section_coord: u8x3) -> &mut T {
+        let row = self.row_of(local_section_coord);
+
+        &mut self.get_or_insert_column_mut(local_section_coord)[row]
+    }
+
+    /// Resolves the whole column containing `local_section_coord`, lazily
+    /// allocating it on first touch. Lets callers that touch multiple
+    /// sections in the same column (e.g. a section and its up/down
+    /// neighbors) pay for the column lookup once and index directly after,
+    /// via [`row_of`].
+    ///
+    /// [`row_of`]: SparseColumnStore::row_of
+    #[inline]
+    pub fn get_or_insert_column_mut(&mut self, local_section_coord: u8x3) -> &mut [T] {
+        let slot = column_slot(local_section_coord);
+        let world_height_sections = self.world_height_sections;
+
+        self.columns[slot]
+            .get_or_insert_with(|| vec![T::default(); world_height_sections].into_boxed_slice())
+    }
+
+    /// Zeroes every already-loaded column's values back to `T::default()`,
+    /// keeping the columns themselves allocated so the next round of
+    /// touches reuses their existing heap buffer instead of paying for a
+    /// fresh allocation per column. Used between frames for caches that
+    /// should not carry values across invocations, but that are touched
+    /// similarly enough frame to frame that reallocating every time would be
+    /// wasted work.
+    pub fn reset(&mut self) {
+        for column in self.columns.iter_mut().filter_map(Option::as_mut) {
+            column.fill(T::default());
+        }
+    }
+
+    /// Drops every loaded column, returning the store to fully empty.
+    pub fn clear(&mut self) {
+        for column in self.columns.iter_mut() {
+            *column = None;
+        }
+    }
+}
+
+impl<T> Default for SparseColumnStore<T> {
+    fn default() -> Self {
+        // the outer table's `None` slots are a safe all-zero bit pattern for
+        // `Option<Box<[T]>>` (`Box` is guaranteed non-null, so `None` is
+        // guaranteed to be represented as a null pointer); zeroing the
+        // allocation directly avoids ever materializing the
+        // `COLUMN_COUNT`-element array on the stack, which a naive
+        // `Box::new([None; COLUMN_COUNT])` would risk.
+        let columns = unsafe {
+            let layout = Layout::new::<[Option<Box<[T]>>; COLUMN_COUNT]>();
+            let ptr = alloc_zeroed(layout) as *mut [Option<Box<[T]>>; COLUMN_COUNT];
+            Box::from_raw(ptr)
+        };
+
+        Self {
+            world_height_sections: 0,
+            y_base: 0,
+            columns,
+        }
+    }
+}
+
+impl<T> InitDefaultInPlace for *mut SparseColumnStore<T> {
+    fn init_default_in_place(self) {
+        unsafe {
+            self.write(SparseColumnStore::default());
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use core::mem::swap;
+
+use crate::collections::ArrayDeque;
+
+/// Number of voxels along one edge of a section.
+const SECTION_EDGE: usize = 16;
+/// Total number of voxels in a section.
+const SECTION_VOLUME: usize = SECTION_EDGE * SECTION_EDGE * SECTION_EDGE;
+
+/// A 16x16x16 bitmap, one bit per voxel, indexed as `x + z * 16 + y * 256`.
+///
+/// Used as the opacity input to [`compute_visibility`]: a set bit means the
+/// voxel is opaque/occluding.
+#[derive(Clone)]
+#[repr(transparent)]
+pub struct BitGrid16x16x16 {
+    words: [u64; SECTION_VOLUME / 64],
+}
+
+impl BitGrid16x16x16 {
+    #[inline(always)]
+    fn index(x: u8, y: u8, z: u8) -> usize {
+        x as usize + z as usize * SECTION_EDGE + y as usize * SECTION_EDGE * SECTION_EDGE
+    }
+
+    #[inline(always)]
+    pub fn get(&self, x: u8, y: u8, z: u8) -> bool {
+        let index = Self::index(x, y, z);
+        (self.words[index / 64] & (1_u64 << (index % 64))) != 0
+    }
+
+    #[inline(always)]
+    pub fn set(&mut self, x: u8, y: u8, z: u8, value: bool) {
+        let index = Self::index(x, y, z);
+        if value {
+            self.words[index / 64] |= 1_u64 << (index % 64);
+        } else {
+            self.words[index / 64] &= !(1_u64 << (index % 64));
+        }
+    }
+}
+
+impl Default for BitGrid16x16x16 {
+    fn default() -> Self {
+        Self {
+            words: [0; SECTION_VOLUME / 64],
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GraphDirection {
+    West = 0,
+    East = 1,
+    Down = 2,
+    Up = 3,
+    North = 4,
+    South = 5,
+}
+
+impl GraphDirection {
+    pub const COUNT: usize = 6;
+    pub const ORDERED: [GraphDirection; Self::COUNT] = [
+        GraphDirection::West,
+        GraphDirection::East,
+        GraphDirection::Down,
+        GraphDirection::Up,
+        GraphDirection::North,
+        GraphDirection::South,
+    ];
+
+    /// Every direction is paired with its opposite at `self ^ 1` because the
+    /// enum is laid out as adjacent (axis, +/-) pairs.
+    #[inline(always)]
+    pub const fn opposite(self) -> GraphDirection {
+        Self::ORDERED[(self as u8 ^ 1) as usize]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct GraphDirectionSet(u8);
+
+impl GraphDirectionSet {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(0b00111111);
+
+    #[inline(always)]
+    pub const fn single(direction: GraphDirection) -> Self {
+        Self(1 << (direction as u8))
+    }
+
+    #[inline(always)]
+    pub fn add(&mut self, direction: GraphDirection) {
+        self.0 |= 1 << (direction as u8);
+    }
+
+    #[inline(always)]
+    pub fn add_all(&mut self, other: GraphDirectionSet) {
+        self.0 |= other.0;
+    }
+
+    #[inline(always)]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl core::ops::BitAnd for GraphDirectionSet {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl core::ops::BitAndAssign for GraphDirectionSet {
+    #[inline(always)]
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl core::ops::BitOr for GraphDirectionSet {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl core::ops::BitOrAssign for GraphDirectionSet {
+    #[inline(always)]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl core::ops::Not for GraphDirectionSet {
+    type Output = Self;
+
+    #[inline(always)]
+    fn not(self) -> Self {
+        Self(!self.0 & GraphDirectionSet::ALL.0)
+    }
+}
+
+// consumes the set, yielding one direction per set bit, lowest first
+impl Iterator for GraphDirectionSet {
+    type Item = GraphDirection;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<GraphDirection> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let bit_index = self.0.trailing_zeros();
+        self.0 &= self.0 - 1;
+
+        Some(GraphDirection::ORDERED[bit_index as usize])
+    }
+}
+
+/// Per-section face connectivity: which outgoing faces are reachable by
+/// light/visibility from each possible incoming face.
+///
+/// This is the data the BFS in `bfs_and_occlusion_cull` consumes through
+/// [`VisibilityData::get_outgoing_directions`] to decide which neighbors of a
+/// visible section are worth enqueueing.
+#[derive(Clone, Copy, Default)]
+#[repr(transparent)]
+pub struct VisibilityData {
+    // connections[face] is the set of faces mutually visible with `face`
+    // through some path of transparent voxels; once `face` is touched by any
+    // region, the set also contains `face` itself.
+    connections: [GraphDirectionSet; GraphDirection::COUNT],
+}
+
+impl VisibilityData {
+    #[inline(always)]
+    pub fn get_outgoing_directions(&self, incoming: GraphDirectionSet) -> GraphDirectionSet {
+        let mut outgoing = GraphDirectionSet::NONE;
+
+        for direction in incoming {
+            outgoing.add_all(self.connections[direction as usize]);
+        }
+
+        outgoing
+    }
+
+    /// Marks every pair of faces in `touched_faces` as mutually visible.
+    fn connect_all(&mut self, touched_faces: GraphDirectionSet) {
+        for face in touched_faces {
+            self.connections[face as usize].add_all(touched_faces);
+        }
+    }
+}
+
+/// Per-voxel queue capacity: the flood fill can never have more voxels in
+/// flight than exist in a section.
+type FloodQueue = ArrayDeque<u16, SECTION_VOLUME>;
+
+/// Builds a [`VisibilityData`] from a section's opacity bitmap via a 6-face
+/// connectivity flood fill.
+///
+/// Every maximal connected region of non-opaque voxels is flooded once; the
+/// set of cube faces it touches are all mutually visible through that
+/// region, so every pair of touched faces is connected in the result. A
+/// fully opaque section therefore produces no connectivity at all, while a
+/// fully transparent one produces full connectivity between all six faces,
+/// matching how the camera's initial BFS node is seeded with
+/// `GraphDirectionSet::ALL`.
+pub fn compute_visibility(opacity: &BitGrid16x16x16) -> VisibilityData {
+    let mut visited = [false; SECTION_VOLUME];
+    let mut visibility = VisibilityData::default();
+
+    let mut queue_a = FloodQueue::default();
+    let mut queue_b = FloodQueue::default();
+
+    for y in 0..SECTION_EDGE as u8 {
+        for z in 0..SECTION_EDGE as u8 {
+            for x in 0..SECTION_EDGE as u8 {
+                let start_index = BitGrid16x16x16::index(x, y, z);
+
+                if visited[start_index] || opacity.get(x, y, z) {
+                    continue;
+                }
+
+                let touched_faces =
+                    flood_fill_region(opacity, &mut visited, x, y, z, &mut queue_a, &mut queue_b);
+
+                visibility.connect_all(touched_faces);
+            }
+        }
+    }
+
+    visibility
+}
+
+fn flood_fill_region(
+    opacity: &BitGrid16x16x16,
+    visited: &mut [bool; SECTION_VOLUME],
+    start_x: u8,
+    start_y: u8,
+    start_z: u8,
+    read_queue: &mut FloodQueue,
+    write_queue: &mut FloodQueue,
+) -> GraphDirectionSet {
+    read_queue.reset();
+    write_queue.reset();
+
+    let mut touched_faces = GraphDirectionSet::NONE;
+    let last = SECTION_EDGE as u8 - 1;
+
+    visited[BitGrid16x16x16::index(start_x, start_y, start_z)] = true;
+    read_queue.push(pack_voxel(start_x, start_y, start_z));
+
+    let mut finished = false;
+    while !finished {
+        finished = true;
+
+        while let Some(&packed) = read_queue.pop() {
+            finished = false;
+
+            let (x, y, z) = unpack_voxel(packed);
+
+            if x == 0 {
+                touched_faces.add(GraphDirection::West);
+            }
+            if x == last {
+                touched_faces.add(GraphDirection::East);
+            }
+            if y == 0 {
+                touched_faces.add(GraphDirection::Down);
+            }
+            if y == last {
+                touched_faces.add(GraphDirection::Up);
+            }
+            if z == 0 {
+                touched_faces.add(GraphDirection::North);
+            }
+            if z == last {
+                touched_faces.add(GraphDirection::South);
+            }
+
+            try_visit_neighbor(opacity, visited, x.checked_sub(1).map(|nx| (nx, y, z)), write_queue);
+            try_visit_neighbor(
+                opacity,
+                visited,
+                (x < last).then(|| (x + 1, y, z)),
+                write_queue,
+            );
+            try_visit_neighbor(opacity, visited, y.checked_sub(1).map(|ny| (x, ny, z)), write_queue);
+            try_visit_neighbor(
+                opacity,
+                visited,
+                (y < last).then(|| (x, y + 1, z)),
+                write_queue,
+            );
+            try_visit_neighbor(opacity, visited, z.checked_sub(1).map(|nz| (x, y, nz)), write_queue);
+            try_visit_neighbor(
+                opacity,
+                visited,
+                (z < last).then(|| (x, y, z + 1)),
+                write_queue,
+            );
+        }
+
+        read_queue.reset();
+        swap(read_queue, write_queue);
+    }
+
+    touched_faces
+}
+
+#[inline]
+fn try_visit_neighbor(
+    opacity: &BitGrid16x16x16,
+    visited: &mut [bool; SECTION_VOLUME],
+    coord: Option<(u8, u8, u8)>,
+    queue: &mut FloodQueue,
+) {
+    let Some((x, y, z)) = coord else {
+        return;
+    };
+
+    let index = BitGrid16x16x16::index(x, y, z);
+
+    if visited[index] || opacity.get(x, y, z) {
+        return;
+    }
+
+    visited[index] = true;
+    queue.push(pack_voxel(x, y, z));
+}
+
+#[inline(always)]
+fn pack_voxel(x: u8, y: u8, z: u8) -> u16 {
+    x as u16 | ((z as u16) << 4) | ((y as u16) << 8)
+}
+
+#[inline(always)]
+fn unpack_voxel(packed: u16) -> (u8, u8, u8) {
+    (
+        (packed & 0xF) as u8,
+        ((packed >> 8) & 0xF) as u8,
+        ((packed >> 4) & 0xF) as u8,
+    )
+}